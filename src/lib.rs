@@ -2,7 +2,7 @@ mod start;
 pub use start::{Start, StartError, StartFn, Starting};
 
 mod supervisor;
-pub use supervisor::Supervisor;
+pub use supervisor::{Supervisor, SupervisorHandle};
 
 // pub mod rest_for_one;
 // pub mod one_for_one;
@@ -12,8 +12,15 @@ pub use simple_rate_limit::RateLimit;
 use async_backplane::{Device, DeviceID, Fault};
 use futures_lite::FutureExt;
 use std::future::Future;
+use std::pin::Pin;
 use std::time::Duration;
 
+/// Identifies a child's slot in a [`Supervisor`], independent of its
+/// position in the supervisor's internal bookkeeping, which can grow
+/// as children are added at runtime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ChildId(pub(crate) u64);
+
 /// A logic for determining which other tasks to restart when one fails.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RecoveryLogic {
@@ -60,6 +67,128 @@ pub enum Restart {
     Failed,
     /// Restart even if it succeeds.
     Always,
+    /// On a fault, suspend this child (and, under a cascading
+    /// [`RecoveryLogic`], its affected siblings) instead of
+    /// restarting it. It stays down until resumed through a
+    /// [`SupervisorHandle`]'s `Resume`/`ResumeAll` control messages.
+    PauseOnFailure,
+}
+
+/// A boxed future returned by a [`Callback`], e.g. `Box::pin(async { ... })`.
+pub type CallbackFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// A boxed closure invoked at a lifecycle point in a child's life.
+pub type Callback = Box<dyn Fn() -> CallbackFuture>;
+
+/// User hooks invoked at well-defined points in a child's lifecycle,
+/// the way an Erlang/OTP-style supervisor exposes them. Any hook left
+/// `None` is simply skipped.
+///
+/// The [`Supervisor`] fires these at precise points in its own flow:
+/// `before_start` immediately before a child is asked to start,
+/// `after_start` only once it has confirmed [`Started::Running`],
+/// `before_restart` at the top of restart handling for the child whose
+/// disconnect is being handled, or that is being force-restarted
+/// (before its old state is torn down, and whether or not its
+/// [`Restart`] policy then restarts it), `after_restart` once that
+/// restart has succeeded, and `after_stop` once a child has been
+/// confirmed stopped. `before_restart`/`after_restart` fire only
+/// for the slot that triggered the restart; under a cascading
+/// [`RecoveryLogic`] its siblings are torn down and brought back up
+/// through the ordinary `after_stop`/`before_start`/`after_start` hooks
+/// instead, not `before_restart`/`after_restart`.
+#[derive(Default)]
+pub struct Callbacks {
+    /// Runs immediately before the child is started.
+    pub before_start: Option<Callback>,
+    /// Runs once the child has confirmed it is [`Started::Running`].
+    pub after_start: Option<Callback>,
+    /// Runs at the top of restart handling, before the child's
+    /// [`Restart`] policy decides whether it is restarted.
+    pub before_restart: Option<Callback>,
+    /// Runs once a restart has succeeded.
+    pub after_restart: Option<Callback>,
+    /// Runs once the child has been confirmed stopped.
+    pub after_stop: Option<Callback>,
+}
+
+/// A supervised child's current position in its lifecycle, as
+/// snapshotted through a [`SupervisorHandle`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChildState {
+    /// Currently running [`Start::start`].
+    Starting,
+    /// Started and running.
+    Running,
+    /// Currently being restarted.
+    Restarting,
+    /// Not running, and not scheduled to restart.
+    Stopped,
+    /// Suspended after a fault under [`Restart::PauseOnFailure`] (its
+    /// own, or under a cascading [`RecoveryLogic`] a sibling's) and
+    /// waiting for an explicit `Resume`/`ResumeAll` control message.
+    Suspended,
+}
+
+/// An observable lifecycle event emitted by a [`Supervisor`] as it
+/// runs, for logging or metrics. Delivery is non-blocking: if the
+/// sink is full or nobody is listening, events are dropped rather
+/// than stalling supervision.
+pub enum SupervisionEvent {
+    /// A child finished starting (or restarting) and is now running.
+    ChildStarted { index: usize, id: ChildId },
+    /// A child finished its work and won't be restarted.
+    ChildCompleted { index: usize },
+    /// A child disconnected with a fault.
+    ChildFaulted { index: usize, fault: Fault },
+    /// A child was successfully restarted. `id` is the slot's stable
+    /// [`ChildId`]; `new_device_id` is the restarted process's fresh
+    /// backplane identity.
+    ChildRestarted { index: usize, id: ChildId, new_device_id: DeviceID, attempt: u32 },
+    /// A restart was denied by the restart rate limiter.
+    RestartThrottled { index: usize },
+    /// A child's restart (after a fault, a clean exit under
+    /// [`Restart::Always`], or a forced restart) triggered a cascade
+    /// restart of other children.
+    CascadeTriggered { logic: RecoveryLogic, from_index: usize },
+    /// A child's shutdown has been confirmed.
+    ShutdownCompleted { index: usize },
+}
+
+/// A delay strategy applied between a child's consecutive restarts,
+/// kept separate from the hard cap enforced by [`Supervisor::restart_rate`].
+/// Spacing restarts out like this keeps a child that instantly
+/// re-crashes from burning through the whole restart budget in
+/// microseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backoff {
+    /// Restart immediately, as before.
+    None,
+    /// The delay grows by a fixed `step` each attempt, up to `max`.
+    Linear { step: Duration, max: Duration },
+    /// The delay grows by `factor` each attempt, up to `max`. When
+    /// `jitter` is set, a random delay in `[0, delay)` is used
+    /// instead, to avoid synchronized restart storms across siblings.
+    Exponential { base: Duration, factor: u32, max: Duration, jitter: bool },
+}
+
+impl Backoff {
+    /// Computes the delay to wait before the restart numbered `attempts`
+    /// (0 for the first restart since the child was last stable).
+    fn delay(&self, attempts: u32) -> Duration {
+        match *self {
+            Backoff::None => Duration::from_secs(0),
+            Backoff::Linear { step, max } => step.saturating_mul(attempts).min(max),
+            Backoff::Exponential { base, factor, max, jitter } => {
+                let delay = base.saturating_mul(factor.saturating_pow(attempts)).min(max);
+                if jitter && delay > Duration::from_secs(0) {
+                    Duration::from_nanos(fastrand::u64(0..delay.as_nanos() as u64))
+                } else {
+                    delay
+                }
+            }
+        }
+    }
 }
 
 /// A structure describing how the supervisor starts and manages a task.
@@ -67,6 +196,12 @@ pub struct Spec {
     pub start: Start,
     pub restart: Restart,
     pub shutdown: Haste,
+    pub callbacks: Callbacks,
+    /// Spacing applied between consecutive restarts of this child.
+    pub backoff: Backoff,
+    /// How long a restarted child must stay up before its backoff
+    /// attempt counter resets to zero.
+    pub stable_after: Duration,
 }
 
 impl Spec {
@@ -76,6 +211,9 @@ impl Spec {
             start,
             restart: Restart::Always,
             shutdown: Haste::Gracefully(Grace::Fixed(Duration::from_secs(5))),
+            callbacks: Callbacks::default(),
+            backoff: Backoff::None,
+            stable_after: Duration::from_secs(10),
         }
     }
 
@@ -96,6 +234,24 @@ impl Spec {
         self.shutdown = shutdown;
         self
     }
+
+    /// Replaces [`callbacks`] with a new [`Callbacks`]
+    pub fn set_callbacks(mut self, callbacks: Callbacks) -> Self {
+        self.callbacks = callbacks;
+        self
+    }
+
+    /// Replaces [`backoff`] with a new [`Backoff`]
+    pub fn set_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Replaces [`stable_after`] with a new [`Duration`]
+    pub fn set_stable_after(mut self, stable_after: Duration) -> Self {
+        self.stable_after = stable_after;
+        self
+    }
 }
 
 /// The Supervisor failed - why?
@@ -6,6 +6,76 @@ use async_io::Timer;
 use futures_lite::*;
 use futures_many::Many;
 use simple_rate_limit::{RateLimit, RateLimiter};
+use std::time::Instant;
+
+/// A control message sent to a supervising [`Supervisor`] through a
+/// [`SupervisorHandle`], letting a caller manage children at runtime.
+enum ControlMessage {
+    AddChild(Spec, async_channel::Sender<ChildId>),
+    TerminateChild(ChildId),
+    RestartChild(ChildId),
+    CountChildren(async_channel::Sender<usize>),
+    Snapshot(async_channel::Sender<Vec<(ChildId, ChildState)>>),
+    Resume(ChildId),
+    ResumeAll,
+}
+
+/// A handle for managing a [`Supervisor`]'s children at runtime, e.g.
+/// starting, terminating or force-restarting them while it supervises.
+/// Obtained from [`Supervisor::handle`] before calling
+/// [`Supervisor::supervise`].
+#[derive(Clone)]
+pub struct SupervisorHandle {
+    sender: async_channel::Sender<ControlMessage>,
+}
+
+impl SupervisorHandle {
+    /// Adds and starts a new child, honoring the supervisor's current
+    /// [`RecoveryLogic`]. Returns the new child's stable [`ChildId`].
+    pub async fn add_child(&self, spec: Spec) -> Option<ChildId> {
+        let (reply, rx) = async_channel::bounded(1);
+        self.sender.send(ControlMessage::AddChild(spec, reply)).await.ok()?;
+        rx.recv().await.ok()
+    }
+
+    /// Shuts a single child down, honoring its [`Haste`], without
+    /// triggering a cascade restart of its siblings.
+    pub async fn terminate_child(&self, id: ChildId) {
+        let _ = self.sender.send(ControlMessage::TerminateChild(id)).await;
+    }
+
+    /// Forces a restart of a child regardless of its [`Restart`] policy.
+    pub async fn restart_child(&self, id: ChildId) {
+        let _ = self.sender.send(ControlMessage::RestartChild(id)).await;
+    }
+
+    /// Counts the children currently running.
+    pub async fn count_children(&self) -> Option<usize> {
+        let (reply, rx) = async_channel::bounded(1);
+        self.sender.send(ControlMessage::CountChildren(reply)).await.ok()?;
+        rx.recv().await.ok()
+    }
+
+    /// Snapshots the current [`ChildState`] of every child, keyed by
+    /// its [`ChildId`].
+    pub async fn snapshot(&self) -> Option<Vec<(ChildId, ChildState)>> {
+        let (reply, rx) = async_channel::bounded(1);
+        self.sender.send(ControlMessage::Snapshot(reply)).await.ok()?;
+        rx.recv().await.ok()
+    }
+
+    /// Resumes a single child suspended under [`Restart::PauseOnFailure`].
+    /// Resets its backoff/attempt state and does not count against the
+    /// restart rate limiter.
+    pub async fn resume_child(&self, id: ChildId) {
+        let _ = self.sender.send(ControlMessage::Resume(id)).await;
+    }
+
+    /// Resumes every currently suspended child.
+    pub async fn resume_all(&self) {
+        let _ = self.sender.send(ControlMessage::ResumeAll).await;
+    }
+}
 
 /// A one-for-one Supervisor
 pub struct Supervisor {
@@ -13,6 +83,23 @@ pub struct Supervisor {
     pub restart_rate: RateLimit,
     specs: Vec<Spec>,
     states: Vec<Option<Line>>,
+    /// Number of consecutive restarts since a slot was last stable,
+    /// consulted by its [`Backoff`].
+    attempts: Vec<u32>,
+    /// When a slot last started running, used to decide whether it
+    /// has been up long enough to reset its `attempts` counter.
+    started_at: Vec<Option<Instant>>,
+    /// Stable identifiers for each slot, parallel to `specs`.
+    ids: Vec<ChildId>,
+    /// Counter used to hand out the next [`ChildId`].
+    next_id: u64,
+    /// Receiving half of a [`SupervisorHandle`]'s control channel, if
+    /// one has been requested via [`Supervisor::handle`].
+    control: Option<async_channel::Receiver<ControlMessage>>,
+    /// Each slot's current position in its lifecycle, parallel to `specs`.
+    phase: Vec<ChildState>,
+    /// Where [`SupervisionEvent`]s are sent, if anyone is listening.
+    events: Option<async_channel::Sender<SupervisionEvent>>,
 }
 
 impl Supervisor {
@@ -23,6 +110,13 @@ impl Supervisor {
             restart_rate: RateLimit::new(5, Duration::from_secs(5)).unwrap(),
             specs: Vec::new(),
             states: Vec::new(),
+            attempts: Vec::new(),
+            started_at: Vec::new(),
+            ids: Vec::new(),
+            next_id: 0,
+            control: None,
+            phase: Vec::new(),
+            events: None,
         }
     }
 
@@ -31,8 +125,47 @@ impl Supervisor {
         self
     }
 
-    pub fn add_task(&mut self, spec: Spec) {
+    /// Registers a sink that receives a [`SupervisionEvent`] for every
+    /// child start, restart, throttle and shutdown. Delivery is
+    /// non-blocking: a full or absent sink simply drops events rather
+    /// than stalling supervision.
+    pub fn set_event_sink(mut self, sink: async_channel::Sender<SupervisionEvent>) -> Self {
+        self.events = Some(sink);
+        self
+    }
+
+    fn emit(&self, event: SupervisionEvent) {
+        if let Some(sink) = &self.events {
+            let _ = sink.try_send(event);
+        }
+    }
+
+    pub fn add_task(&mut self, spec: Spec) -> ChildId {
+        self.push_spec(spec)
+    }
+
+    /// Creates a [`SupervisorHandle`] for managing this supervisor's
+    /// children at runtime. Must be called before [`Supervisor::supervise`],
+    /// since that consumes `self`.
+    pub fn handle(&mut self) -> SupervisorHandle {
+        let (sender, receiver) = async_channel::unbounded();
+        self.control = Some(receiver);
+        SupervisorHandle { sender }
+    }
+
+    fn push_spec(&mut self, spec: Spec) -> ChildId {
+        let id = ChildId(self.next_id);
+        self.next_id += 1;
         self.specs.push(spec);
+        self.attempts.push(0);
+        self.started_at.push(None);
+        self.ids.push(id);
+        self.phase.push(ChildState::Stopped);
+        id
+    }
+
+    fn index_of(&self, id: ChildId) -> Option<usize> {
+        self.ids.iter().position(|&i| i == id)
     }
 
     pub async fn supervise(
@@ -67,15 +200,38 @@ impl Supervisor {
         device: &mut Device,
         index: usize
     ) -> Result<Option<Line>, Crash<SupervisionError>> {
+        if let Some(cb) = &self.specs[index].callbacks.before_start {
+            cb().await;
+        }
+        self.phase[index] = ChildState::Starting;
         let d = Device::new();
         device.link(&d, LinkMode::Monitor);
         let line = d.line();
-        self.specs[index].start.start(d).await
-            .map_err(|e| Crash::Error(SupervisionError::StartupFailed(index, e)))
-            .map(|s| match s {
-                Started::Completed => None,
-                Started::Running => Some(line),
-            })
+        let started = match self.specs[index].start.start(d).await {
+            Ok(started) => started,
+            Err(e) => {
+                self.phase[index] = ChildState::Stopped;
+                return Err(Crash::Error(SupervisionError::StartupFailed(index, e)));
+            }
+        };
+        match started {
+            Started::Running => {
+                self.started_at[index] = Some(Instant::now());
+                self.phase[index] = ChildState::Running;
+                self.emit(SupervisionEvent::ChildStarted { index, id: self.ids[index] });
+                if let Some(cb) = &self.specs[index].callbacks.after_start {
+                    cb().await;
+                }
+            }
+            Started::Completed => {
+                self.phase[index] = ChildState::Stopped;
+                self.emit(SupervisionEvent::ChildCompleted { index });
+            }
+        }
+        Ok(match started {
+            Started::Completed => None,
+            Started::Running => Some(line),
+        })
     }
 
     async fn watch(
@@ -84,19 +240,158 @@ impl Supervisor {
     ) -> Result<(), Crash<SupervisionError>> {
         let mut limiter = RateLimiter::new(self.restart_rate);
         let mut device = device;
-        while let Some(message) = device.next().await {
-            match message {
-                Shutdown(id) => {
+        loop {
+            let next = if let Some(control) = &self.control {
+                async { Ok(device.next().await) }
+                    .or(async { Err(control.recv().await.ok()) })
+                    .await
+            } else {
+                Ok(device.next().await)
+            };
+            match next {
+                Ok(Some(Shutdown(id))) => {
                     device.disconnect(None);
                     return Err(Crash::PowerOff(id));
                 }
-                Disconnected(id, result) => {
+                Ok(Some(Disconnected(id, result))) => {
                     let ret = self.disconnected(&mut device, id, result, &mut limiter).await;
                     if let Err(crash) = ret { return Err(crash); }
                 }
+                Ok(None) => return Ok(()), // Not found
+                Err(Some(message)) => {
+                    if let Err(crash) = self.handle_control(&mut device, message, &mut limiter).await {
+                        return Err(crash);
+                    }
+                }
+                Err(None) => { self.control = None; } // sender dropped
             }
         }
-        Ok(()) // Not found
+    }
+
+    async fn handle_control(
+        &mut self,
+        device: &mut Device,
+        message: ControlMessage,
+        limiter: &mut RateLimiter,
+    ) -> Result<(), Crash<SupervisionError>> {
+        match message {
+            ControlMessage::AddChild(spec, reply) => {
+                let id = self.push_spec(spec);
+                let index = self.specs.len() - 1;
+                let line = self.start_link(device, index).await.unwrap_or(None);
+                self.states.push(line);
+                let _ = reply.send(id).await;
+            }
+            ControlMessage::TerminateChild(id) => {
+                if let Some(index) = self.index_of(id) {
+                    self.terminate_slot(device, index).await;
+                }
+            }
+            ControlMessage::RestartChild(id) => {
+                if let Some(index) = self.index_of(id) {
+                    match self.force_restart(device, index, limiter).await {
+                        // Throttling doesn't tear down any siblings; a forced
+                        // restart hitting the rate limit leaves only this
+                        // child stopped (it was stopped before the limiter
+                        // was checked), not a reason to end supervision of
+                        // everyone else.
+                        Err(Crash::Error(SupervisionError::Throttled)) => {}
+                        other => other?,
+                    }
+                }
+            }
+            ControlMessage::CountChildren(reply) => {
+                let count = self.states.iter().filter(|s| s.is_some()).count();
+                let _ = reply.send(count).await;
+            }
+            ControlMessage::Snapshot(reply) => {
+                let snapshot = self.ids.iter().cloned().zip(self.phase.iter().cloned()).collect();
+                let _ = reply.send(snapshot).await;
+            }
+            ControlMessage::Resume(id) => {
+                if let Some(index) = self.index_of(id) {
+                    let _ = self.resume(device, index).await;
+                }
+            }
+            ControlMessage::ResumeAll => {
+                let suspended: Vec<usize> = (0..self.phase.len())
+                    .filter(|&index| self.phase[index] == ChildState::Suspended)
+                    .collect();
+                for index in suspended {
+                    let _ = self.resume(device, index).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resumes a single suspended slot, resetting its backoff state.
+    /// Does not consult the restart rate limiter: a resume is an
+    /// explicit operator action, not an automatic restart.
+    async fn resume(&mut self, device: &mut Device, index: usize) -> Result<(), Crash<SupervisionError>> {
+        if self.phase[index] != ChildState::Suspended {
+            return Ok(());
+        }
+        self.attempts[index] = 0;
+        self.started_at[index] = None;
+        match self.start_link(device, index).await {
+            Ok(line) => { self.states[index] = line; Ok(()) }
+            Err(crash) => Err(crash),
+        }
+    }
+
+    /// Shuts a single child down, honoring its [`Haste`], without
+    /// draining or restarting any of its siblings.
+    async fn terminate_slot(&mut self, device: &mut Device, index: usize) {
+        if self.stop_slot(device, index).await {
+            self.after_stop(index).await;
+        }
+    }
+
+    /// Takes a slot's running [`Line`], if any, and shuts it down
+    /// honoring its [`Haste`]. Unlike [`Supervisor::terminate_slot`],
+    /// does not touch the slot's [`ChildState`] or fire `after_stop`,
+    /// so callers can follow up with a restart instead of a permanent
+    /// stop. Returns whether a line was actually running to stop.
+    async fn stop_slot(&mut self, device: &mut Device, index: usize) -> bool {
+        let my_id = device.device_id();
+        if let Some(line) = self.states[index].take() {
+            let id = line.device_id();
+            #[allow(unused_must_use)]
+            match self.specs[index].shutdown {
+                Haste::Quickly => { line.send(Shutdown(my_id)); }
+                Haste::Gracefully(Grace::Forever) => {
+                    line.send(Shutdown(my_id));
+                    self.wait_for_one(device, id, None).await;
+                }
+                Haste::Gracefully(Grace::Fixed(when)) => {
+                    line.send(Shutdown(my_id));
+                    self.wait_for_one(device, id, Some(when)).await;
+                }
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Waits for a single device to disconnect, or for its grace
+    /// period to elapse if one is given, ignoring everything else
+    /// (including our own shutdown requests) in the meantime.
+    async fn wait_for_one(&mut self, device: &mut Device, id: DeviceID, grace: Option<Duration>) {
+        let waiting = async {
+            loop {
+                match device.next().await {
+                    Some(Message::Disconnected(got, _)) if got == id => return,
+                    Some(_) => continue,
+                    None => return,
+                }
+            }
+        };
+        match grace {
+            Some(when) => { waiting.or(async { Timer::new(when).await; }).await; }
+            None => waiting.await,
+        }
     }
 
     async fn disconnected(
@@ -124,15 +419,87 @@ impl Supervisor {
         result: Option<Fault>,
         limiter: &mut RateLimiter
     ) -> Result<(), Crash<SupervisionError>> {
+        if let Some(cb) = &self.specs[index].callbacks.before_restart {
+            cb().await;
+        }
+        self.phase[index] = ChildState::Restarting;
         self.states[index].take().unwrap();
-        match self.specs[index].restart {
-            Restart::Never => Ok(()),
-            Restart::Always => self.restart(device, index, limiter).await,
-            Restart::Failed => {
-                if result.is_some() { self.restart(device, index, limiter).await }
-                else { Ok(()) }
+        self.maybe_reset_attempts(index);
+        if let Some(fault) = result {
+            self.emit(SupervisionEvent::ChildFaulted { index, fault });
+            match self.specs[index].restart {
+                Restart::Never => { self.phase[index] = ChildState::Stopped; Ok(()) }
+                Restart::Always | Restart::Failed => self.restart(device, index, limiter).await,
+                Restart::PauseOnFailure => self.suspend(device, index).await,
             }
+        } else {
+            match self.specs[index].restart {
+                Restart::Always => self.restart(device, index, limiter).await,
+                Restart::Never | Restart::Failed | Restart::PauseOnFailure => {
+                    self.phase[index] = ChildState::Stopped;
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Resets a slot's `attempts` counter once it has been up long
+    /// enough, per its [`Spec::stable_after`], to no longer count
+    /// towards its [`Backoff`].
+    fn maybe_reset_attempts(&mut self, index: usize) {
+        if let Some(started) = self.started_at[index].take() {
+            if started.elapsed() >= self.specs[index].stable_after {
+                self.attempts[index] = 0;
+            }
+        }
+    }
+
+    /// Suspends a faulted child instead of restarting it. Under a
+    /// cascading [`RecoveryLogic`], the siblings that would otherwise
+    /// be restarted are shut down and suspended too, rather than
+    /// being brought back up, mirroring the same shutdown range
+    /// `restart` uses for its cascade.
+    async fn suspend(&mut self, device: &mut Device, index: usize) -> Result<(), Crash<SupervisionError>> {
+        self.phase[index] = ChildState::Suspended;
+        match self.logic {
+            RecoveryLogic::Isolated => {}
+            RecoveryLogic::CascadeNewer => {
+                self.shut_down(device, index + 1).await;
+                for i in (index + 1)..self.specs.len() {
+                    self.states.push(None);
+                    self.phase[i] = ChildState::Suspended;
+                }
+            }
+            RecoveryLogic::CascadeAll => {
+                self.shut_down(device, 0).await;
+                for i in 0..self.specs.len() {
+                    self.states.push(None);
+                    self.phase[i] = ChildState::Suspended;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forces a child back through a restart regardless of its
+    /// [`Restart`] policy, e.g. in response to a [`SupervisorHandle`]'s
+    /// `RestartChild` control message. If the child is still running,
+    /// it is stopped first (honoring its [`Haste`], the same way
+    /// [`Supervisor::terminate_slot`] would) so the forced restart
+    /// never races with the old child's own disconnect.
+    async fn force_restart(
+        &mut self,
+        device: &mut Device,
+        index: usize,
+        limiter: &mut RateLimiter,
+    ) -> Result<(), Crash<SupervisionError>> {
+        if let Some(cb) = &self.specs[index].callbacks.before_restart {
+            cb().await;
         }
+        self.phase[index] = ChildState::Restarting;
+        self.stop_slot(device, index).await;
+        self.maybe_reset_attempts(index);
+        self.restart(device, index, limiter).await
     }
 
     async fn restart(
@@ -142,7 +509,12 @@ impl Supervisor {
         limiter: &mut RateLimiter
     ) -> Result<(), Crash<SupervisionError>> {
         if limiter.check() {
-            match self.logic {
+            let delay = self.specs[index].backoff.delay(self.attempts[index]);
+            if delay > Duration::from_secs(0) {
+                Timer::new(delay).await;
+            }
+            self.attempts[index] += 1;
+            let result = match self.logic {
                 RecoveryLogic::Isolated => {
                     match self.start_link(device, index).await {
                         Ok(line) => {
@@ -156,21 +528,39 @@ impl Supervisor {
                     }
                 }
                 RecoveryLogic::CascadeNewer => {
-                    self.shut_down(device, index + 1).await;
+                    self.emit(SupervisionEvent::CascadeTriggered { logic: self.logic, from_index: index });
+                    self.shut_down(device, index).await;
                     self.start_up(device, index).await
                 }
                 RecoveryLogic::CascadeAll => {
+                    self.emit(SupervisionEvent::CascadeTriggered { logic: self.logic, from_index: index });
                     self.shut_down(device, 0).await;
                     self.start_up(device, 0).await
                 }
+            };
+            if result.is_ok() {
+                if let Some(line) = &self.states[index] {
+                    self.emit(SupervisionEvent::ChildRestarted {
+                        index,
+                        id: self.ids[index],
+                        new_device_id: line.device_id(),
+                        attempt: self.attempts[index],
+                    });
+                }
+                if let Some(cb) = &self.specs[index].callbacks.after_restart {
+                    cb().await;
+                }
             }
+            result
         } else {
+            self.phase[index] = ChildState::Stopped;
+            self.emit(SupervisionEvent::RestartThrottled { index });
             Err(Crash::Error(SupervisionError::Throttled))
         }
     }
 
     async fn shut_down(&mut self, device: &mut Device, start_index: usize) {
-        let mut waiting: Vec<Option<DeviceID>> = Vec::new();
+        let mut waiting: Vec<Option<(usize, DeviceID)>> = Vec::new();
         let mut timers = Many::new();
         self.start_shut_down(device.device_id(), start_index, &mut waiting, &mut timers).await;
         let mut needed = waiting.len();
@@ -178,10 +568,11 @@ impl Supervisor {
             match self.next_shutdown_message(device, &mut timers).await {
                 ShuttingDown::Remove(id) => {
                     for x in waiting.iter_mut() {
-                        if let Some(y) = x {
-                            if *y == id {
+                        if let Some((index, y)) = *x {
+                            if y == id {
                                 *x = None;
                                 needed -= 1;
+                                self.after_stop(index).await;
                             }
                         }
                     }
@@ -195,10 +586,11 @@ impl Supervisor {
             if let Some(message) = device.next().await {
                 if let Message::Disconnected(id, _) = message {
                     for x in waiting.iter_mut() {
-                        if let Some(y) = x {
-                            if *y == id {
+                        if let Some((index, y)) = *x {
+                            if y == id {
                                 *x = None;
                                 needed -= 1;
+                                self.after_stop(index).await;
                             }
                         }
                     }
@@ -211,7 +603,7 @@ impl Supervisor {
         &mut self,
         my_id: DeviceID,
         start_index: usize,
-        waiting: &mut Vec<Option<DeviceID>>,
+        waiting: &mut Vec<Option<(usize, DeviceID)>>,
         timers: &mut Many<future::Boxed<DeviceID>>
     ) {
         for (i, state) in self.states.drain(start_index..).enumerate().rev() {
@@ -222,11 +614,11 @@ impl Supervisor {
                 match self.specs[index].shutdown {
                     Haste::Quickly => { line.send(Shutdown(my_id)); }
                     Haste::Gracefully(Grace::Forever) => {
-                        waiting.push(Some(line.device_id()));
+                        waiting.push(Some((index, line.device_id())));
                         line.send(Shutdown(my_id));
                     }
                     Haste::Gracefully(Grace::Fixed(when)) => {
-                        waiting.push(Some(line.device_id()));
+                        waiting.push(Some((index, line.device_id())));
                         timers.push(timer(when, id).boxed());
                         line.send(Shutdown(my_id));
                     }
@@ -235,6 +627,19 @@ impl Supervisor {
         }
     }
 
+    /// Fires once a child's disconnect has been confirmed, either by
+    /// an explicit `Disconnected` message or its grace timer firing.
+    /// A [`Haste::Quickly`] child stopped through
+    /// [`Supervisor::terminate_slot`] is not waited for: this fires as
+    /// soon as its shutdown request has been sent.
+    async fn after_stop(&mut self, index: usize) {
+        self.phase[index] = ChildState::Stopped;
+        self.emit(SupervisionEvent::ShutdownCompleted { index });
+        if let Some(cb) = &self.specs[index].callbacks.after_stop {
+            cb().await;
+        }
+    }
+
     async fn next_shutdown_message(&mut self, device: &mut Device, timers: &mut Many<future::Boxed<DeviceID>>) -> ShuttingDown {
         loop {
             let ret = async { Ok(device.next().await) }.or(async { Err(timers.next().await) }).await;